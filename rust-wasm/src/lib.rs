@@ -2,8 +2,18 @@
 //!
 //! This module provides Blake3 hashing with multi-threaded support in WASM
 //! using wasm-bindgen-rayon for Web Worker threading.
+//!
+//! Keyed hashing and key derivation depend on the `blake3` crate's
+//! `zeroize` feature (`blake3 = { features = ["zeroize"] }` in
+//! Cargo.toml) so that `Hasher`, `Output`, and `OutputReader` scrub the
+//! raw key they hold internally when dropped. The local `.zeroize()`
+//! calls on key bytes in this file only cover the transient copies made
+//! at the wasm boundary; they are not sufficient on their own.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use wasm_bindgen::prelude::*;
+use zeroize::Zeroize;
 
 // Re-export rayon thread pool init for JavaScript
 pub use wasm_bindgen_rayon::init_thread_pool;
@@ -60,27 +70,217 @@ pub fn hash_rayon_xof(data: &[u8], output_len: usize) -> Vec<u8> {
     output
 }
 
+/// Input size, in bytes, below which `hash_auto` prefers the
+/// single-threaded path over paying rayon's thread/worker overhead.
+const DEFAULT_AUTO_THRESHOLD: usize = 128 * 1024;
+
+static AUTO_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_AUTO_THRESHOLD);
+
+/// Hash data, picking `hash` or `update_rayon` based on input size and
+/// thread count. Tune the cutoff with `set_auto_threshold`.
+#[wasm_bindgen]
+pub fn hash_auto(data: &[u8]) -> Vec<u8> {
+    let threshold = AUTO_THRESHOLD.load(Ordering::Relaxed);
+    if data.len() < threshold || rayon::current_num_threads() <= 1 {
+        blake3::hash(data).as_bytes().to_vec()
+    } else {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_rayon(data);
+        hasher.finalize().as_bytes().to_vec()
+    }
+}
+
+/// Get the current size threshold used by `hash_auto`
+#[wasm_bindgen]
+pub fn get_auto_threshold() -> usize {
+    AUTO_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// Set the size threshold used by `hash_auto`, so integrators can retune
+/// the sequential/parallel cutoff for their deployment
+#[wasm_bindgen]
+pub fn set_auto_threshold(threshold: usize) {
+    AUTO_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
 /// Keyed hash (MAC mode)
 #[wasm_bindgen]
 pub fn keyed_hash(key: &[u8], data: &[u8]) -> Result<Vec<u8>, JsValue> {
     if key.len() != 32 {
         return Err(JsValue::from_str("Key must be 32 bytes"));
     }
-    let key_array: [u8; 32] = key.try_into().unwrap();
-    Ok(blake3::keyed_hash(&key_array, data).as_bytes().to_vec())
+    let mut key_array: [u8; 32] = key.try_into().unwrap();
+    let result = blake3::keyed_hash(&key_array, data).as_bytes().to_vec();
+    key_array.zeroize();
+    Ok(result)
+}
+
+/// Keyed hash (MAC mode) with a custom output length
+#[wasm_bindgen]
+pub fn keyed_hash_xof(key: &[u8], data: &[u8], output_len: usize) -> Result<Vec<u8>, JsValue> {
+    if key.len() != 32 {
+        return Err(JsValue::from_str("Key must be 32 bytes"));
+    }
+    let mut key_array: [u8; 32] = key.try_into().unwrap();
+    let mut output = vec![0u8; output_len];
+    blake3::Hasher::new_keyed(&key_array)
+        .update(data)
+        .finalize_xof()
+        .fill(&mut output);
+    key_array.zeroize();
+    Ok(output)
+}
+
+/// Keyed hash (MAC mode) using Rayon parallelism
+#[wasm_bindgen]
+pub fn keyed_hash_rayon(key: &[u8], data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    if key.len() != 32 {
+        return Err(JsValue::from_str("Key must be 32 bytes"));
+    }
+    let mut key_array: [u8; 32] = key.try_into().unwrap();
+    let mut hasher = blake3::Hasher::new_keyed(&key_array);
+    hasher.update_rayon(data);
+    let result = hasher.finalize().as_bytes().to_vec();
+    key_array.zeroize();
+    Ok(result)
 }
 
-/// Derive key (KDF mode)
+/// Derive key (KDF mode). Takes ownership of `key_material` so the copy
+/// handed across the JS/WASM boundary can be scrubbed once it's hashed.
 #[wasm_bindgen]
-pub fn derive_key(context: &str, key_material: &[u8], output_len: usize) -> Vec<u8> {
+pub fn derive_key(context: &str, mut key_material: Vec<u8>, output_len: usize) -> Vec<u8> {
     let mut output = vec![0u8; output_len];
     blake3::Hasher::new_derive_key(context)
-        .update(key_material)
+        .update(&key_material)
         .finalize_xof()
         .fill(&mut output);
+    key_material.zeroize();
     output
 }
 
+/// Incremental Blake3 hasher for streaming input from JS without buffering
+/// the whole payload in WASM memory up front.
+#[wasm_bindgen]
+pub struct Blake3Hasher {
+    inner: blake3::Hasher,
+    // Chunk held back by `update_rayon_pipelined` so it can be hashed on
+    // the *next* call, overlapping with the JS-side read of the chunk
+    // after that. See `update_rayon_pipelined` for the full scheme.
+    pending: Option<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl Blake3Hasher {
+    /// Create a new hasher in the default (unkeyed) mode
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Blake3Hasher {
+        Blake3Hasher {
+            inner: blake3::Hasher::new(),
+            pending: None,
+        }
+    }
+
+    /// Create a new hasher in keyed mode (MAC)
+    pub fn new_keyed(key: &[u8]) -> Result<Blake3Hasher, JsValue> {
+        if key.len() != 32 {
+            return Err(JsValue::from_str("Key must be 32 bytes"));
+        }
+        let mut key_array: [u8; 32] = key.try_into().unwrap();
+        let inner = blake3::Hasher::new_keyed(&key_array);
+        key_array.zeroize();
+        Ok(Blake3Hasher {
+            inner,
+            pending: None,
+        })
+    }
+
+    /// Create a new hasher in key derivation mode (KDF)
+    pub fn new_derive_key(context: &str) -> Blake3Hasher {
+        Blake3Hasher {
+            inner: blake3::Hasher::new_derive_key(context),
+            pending: None,
+        }
+    }
+
+    /// Feed more input into the hasher
+    pub fn update(&mut self, data: &[u8]) {
+        self.flush_pending();
+        self.inner.update(data);
+    }
+
+    /// Feed more input into the hasher using Rayon parallelism
+    pub fn update_rayon(&mut self, data: &[u8]) {
+        self.flush_pending();
+        self.inner.update_rayon(data);
+    }
+
+    /// Feed the next chunk of a large input. Rather than hashing `chunk`
+    /// immediately, this hashes whichever chunk was passed to the
+    /// *previous* call (if any) and holds `chunk` back for the call after
+    /// that, so the rayon hash of buffer N runs inside the call that passes
+    /// buffer N+1 and can overlap with work JS started on buffer N+2 before
+    /// making that call (e.g. an async read already in flight).
+    /// Use ~1 MiB chunks; call `finalize` or `finalize_xof` when done to
+    /// hash the final pending chunk.
+    pub fn update_rayon_pipelined(&mut self, chunk: &[u8]) {
+        if let Some(previous) = self.pending.take() {
+            self.inner.update_rayon(&previous);
+        }
+        self.pending = Some(chunk.to_vec());
+    }
+
+    /// Finalize and return the 32-byte hash
+    pub fn finalize(&mut self) -> Vec<u8> {
+        self.flush_pending();
+        self.inner.finalize().as_bytes().to_vec()
+    }
+
+    /// Finalize into a seekable extended-output (XOF) reader
+    pub fn finalize_xof(&mut self) -> Blake3XofReader {
+        self.flush_pending();
+        Blake3XofReader {
+            inner: self.inner.finalize_xof(),
+        }
+    }
+}
+
+impl Blake3Hasher {
+    /// Hash any chunk left over from `update_rayon_pipelined`
+    fn flush_pending(&mut self) {
+        if let Some(chunk) = self.pending.take() {
+            self.inner.update_rayon(&chunk);
+        }
+    }
+}
+
+impl Default for Blake3Hasher {
+    fn default() -> Self {
+        Blake3Hasher::new()
+    }
+}
+
+/// Seekable reader over a Blake3 extended-output stream, for pulling
+/// arbitrary ranges of a long keystream without re-hashing the input.
+#[wasm_bindgen]
+pub struct Blake3XofReader {
+    inner: blake3::OutputReader,
+}
+
+#[wasm_bindgen]
+impl Blake3XofReader {
+    /// Read the next `buf_len` bytes from the current position
+    pub fn fill(&mut self, buf_len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; buf_len];
+        self.inner.fill(&mut buf);
+        buf
+    }
+
+    /// Seek to an absolute byte offset in the output stream
+    pub fn set_position(&mut self, pos: u64) {
+        self.inner.set_position(pos);
+    }
+}
+
 /// Get number of rayon threads
 #[wasm_bindgen]
 pub fn get_thread_count() -> usize {
@@ -144,4 +344,125 @@ mod tests {
         let result = hash(&[]);
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_hash_auto_matches_hash_for_small_input() {
+        let data = b"small input";
+        assert_eq!(hash_auto(data), hash(data));
+    }
+
+    #[test]
+    fn test_hash_auto_matches_hash_rayon_for_large_input() {
+        let data = vec![0x42u8; 1024 * 1024];
+        assert_eq!(hash_auto(&data), hash_rayon(&data));
+    }
+
+    #[test]
+    fn test_auto_threshold_roundtrip() {
+        let original = get_auto_threshold();
+        set_auto_threshold(4096);
+        assert_eq!(get_auto_threshold(), 4096);
+        set_auto_threshold(original);
+    }
+
+    #[test]
+    fn test_blake3_hasher_matches_hash() {
+        let data = b"hello world";
+        let mut hasher = Blake3Hasher::new();
+        hasher.update(data);
+        assert_eq!(hasher.finalize(), hash(data));
+    }
+
+    #[test]
+    fn test_blake3_hasher_update_rayon_matches_hash() {
+        let data = vec![0x07u8; 1024 * 1024];
+        let mut hasher = Blake3Hasher::new();
+        hasher.update_rayon(&data);
+        assert_eq!(hasher.finalize(), hash(&data));
+    }
+
+    #[test]
+    fn test_blake3_hasher_keyed_matches_keyed_hash() {
+        let key = [0x11u8; 32];
+        let data = b"authenticate me";
+        let mut hasher = Blake3Hasher::new_keyed(&key).unwrap();
+        hasher.update(data);
+        assert_eq!(hasher.finalize(), keyed_hash(&key, data).unwrap());
+    }
+
+    #[test]
+    fn test_blake3_hasher_new_keyed_rejects_bad_key_length() {
+        assert!(Blake3Hasher::new_keyed(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_blake3_hasher_derive_key_matches_derive_key() {
+        let context = "example context";
+        let key_material = b"input key material".to_vec();
+        let mut hasher = Blake3Hasher::new_derive_key(context);
+        hasher.update(&key_material);
+        assert_eq!(hasher.finalize(), derive_key(context, key_material, 32));
+    }
+
+    #[test]
+    fn test_xof_reader_fill_matches_hash_xof() {
+        let data = b"xof test data";
+        let mut hasher = Blake3Hasher::new();
+        hasher.update(data);
+        let mut reader = hasher.finalize_xof();
+        assert_eq!(reader.fill(64), hash_xof(data, 64));
+    }
+
+    #[test]
+    fn test_xof_reader_set_position_seeks_into_the_same_stream() {
+        let data = b"xof seek test";
+
+        let mut hasher = Blake3Hasher::new();
+        hasher.update(data);
+        let mut seeked_reader = hasher.finalize_xof();
+        seeked_reader.set_position(64);
+        let seeked = seeked_reader.fill(32);
+
+        let mut hasher = Blake3Hasher::new();
+        hasher.update(data);
+        let mut full_reader = hasher.finalize_xof();
+        let full = full_reader.fill(96);
+
+        assert_eq!(seeked, full[64..96]);
+    }
+
+    #[test]
+    fn test_update_rayon_pipelined_matches_sequential_hash() {
+        let chunk_a = vec![0x01u8; 64 * 1024];
+        let chunk_b = vec![0x02u8; 64 * 1024];
+        let chunk_c = vec![0x03u8; 64 * 1024];
+
+        let mut hasher = Blake3Hasher::new();
+        hasher.update_rayon_pipelined(&chunk_a);
+        hasher.update_rayon_pipelined(&chunk_b);
+        hasher.update_rayon_pipelined(&chunk_c);
+        let pipelined = hasher.finalize();
+
+        let mut expected = blake3::Hasher::new();
+        expected.update(&chunk_a);
+        expected.update(&chunk_b);
+        expected.update(&chunk_c);
+        assert_eq!(pipelined, expected.finalize().as_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_update_flushes_pending_pipelined_chunk_in_order() {
+        let chunk_a = vec![0xAAu8; 1024];
+        let chunk_b = b"interleaved".to_vec();
+
+        let mut hasher = Blake3Hasher::new();
+        hasher.update_rayon_pipelined(&chunk_a);
+        hasher.update(&chunk_b);
+        let result = hasher.finalize();
+
+        let mut expected = blake3::Hasher::new();
+        expected.update(&chunk_a);
+        expected.update(&chunk_b);
+        assert_eq!(result, expected.finalize().as_bytes().to_vec());
+    }
 }